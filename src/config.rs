@@ -1,11 +1,61 @@
+use std::sync::Arc;
+
 use crate::smtp::args::{Domain, ForwardPath, LocalPart, Path};
+use crate::smtp::{NoMxResolver, Resolver};
+
+/// How strongly a relay requires STARTTLS before a message may be handed
+/// off to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsPolicy {
+	/// Refuse to relay in cleartext; if the peer doesn't advertise
+	/// `STARTTLS`, the message is undeliverable to this relay.
+	Require,
+	/// Upgrade to TLS when the peer advertises `STARTTLS`, but relay in
+	/// cleartext otherwise.
+	Prefer,
+	/// Never attempt STARTTLS, even if the peer advertises it.
+	Disable,
+}
+
+impl Default for TlsPolicy {
+	fn default() -> Self {
+		Self::Prefer
+	}
+}
+
+/// A username/password pair to authenticate to a relay with, via SMTP AUTH.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+	pub username: String,
+	pub password: String,
+}
+
+/// A smarthost we can hand foreign mail off to.
+#[derive(Debug, Clone)]
+pub struct Relay {
+	pub domain: Domain,
+	pub tls: TlsPolicy,
+	pub auth: Option<Credentials>,
+}
 
 #[derive(Clone)]
 pub struct Config {
 	//TODO: Properly load a config and don't have this be public!
 	pub hostnames: Vec<Domain>,
-	pub relays: Vec<Domain>,
+	pub relays: Vec<Relay>,
 	pub users: Vec<LocalPart>,
+	pub resolver: Arc<dyn Resolver>,
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			hostnames: Vec::new(),
+			relays: Vec::new(),
+			users: Vec::new(),
+			resolver: Arc::new(NoMxResolver),
+		}
+	}
 }
 
 impl Config {
@@ -19,4 +69,100 @@ impl Config {
 	fn path_is_local(&self, path: &Path) -> bool {
 		self.hostnames.contains(&path.domain)
 	}
+
+	/// The relay configured for a domain, if any.
+	pub fn relay_for(&self, domain: &Domain) -> Option<&Relay> {
+		self.relays.iter().find(|relay| &relay.domain == domain)
+	}
+
+	/// The ordered hosts to try delivering `forward` to: our configured
+	/// relays if we have any, otherwise the recipient domain's MX hosts
+	/// (falling back to the domain itself per RFC 5321 §5.1 when it has no
+	/// MX record).
+	pub fn route(&self, forward: &ForwardPath) -> Vec<Domain> {
+		if !self.relays.is_empty() {
+			return self.relays.iter().map(|relay| relay.domain.clone()).collect();
+		}
+
+		let domain = match forward {
+			ForwardPath::Postmaster => return self.hostnames.clone(),
+			ForwardPath::Regular(path) => &path.domain,
+		};
+
+		let mx_hosts = self.resolver.lookup_mx(domain);
+		if mx_hosts.is_empty() {
+			vec![domain.clone()]
+		} else {
+			mx_hosts
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct FakeResolver(Vec<Domain>);
+
+	impl Resolver for FakeResolver {
+		fn lookup_mx(&self, _domain: &Domain) -> Vec<Domain> {
+			self.0.clone()
+		}
+	}
+
+	fn forward_to(domain: &str) -> ForwardPath {
+		ForwardPath::Regular(Path {
+			local_part: LocalPart("bob".into()),
+			domain: Domain(domain.into()),
+		})
+	}
+
+	#[test]
+	fn routes_to_configured_relays_when_present() {
+		let config = Config {
+			relays: vec![Relay {
+				domain: Domain("smarthost.example.com".into()),
+				tls: TlsPolicy::default(),
+				auth: None,
+			}],
+			..Config::default()
+		};
+
+		assert_eq!(
+			config.route(&forward_to("elsewhere.example.org")),
+			vec![Domain("smarthost.example.com".into())]
+		);
+	}
+
+	#[test]
+	fn falls_back_to_the_domain_itself_with_no_mx_records() {
+		let config = Config::default(); // NoMxResolver
+		assert_eq!(
+			config.route(&forward_to("example.org")),
+			vec![Domain("example.org".into())]
+		);
+	}
+
+	#[test]
+	fn uses_mx_records_when_the_resolver_finds_them() {
+		let config = Config {
+			resolver: std::sync::Arc::new(FakeResolver(vec![Domain("mx1.example.org".into())])),
+			..Config::default()
+		};
+
+		assert_eq!(
+			config.route(&forward_to("example.org")),
+			vec![Domain("mx1.example.org".into())]
+		);
+	}
+
+	#[test]
+	fn postmaster_routes_to_our_own_hostnames() {
+		let config = Config {
+			hostnames: vec![Domain("mail.example.com".into())],
+			..Config::default()
+		};
+
+		assert_eq!(config.route(&ForwardPath::Postmaster), vec![Domain("mail.example.com".into())]);
+	}
 }
@@ -0,0 +1,32 @@
+use crate::smtp::args::{ForwardPath, ReversePath};
+
+/// A message as it's held internally, independent of whether it's bound for
+/// a local mailbox or a foreign relay.
+#[derive(Debug, Clone, Default)]
+pub struct Message {
+	pub reverse_path: ReversePath,
+	pub forward_paths: Vec<ForwardPath>,
+	pub data: Vec<String>,
+}
+
+impl Message {
+	pub fn push_line(&mut self, line: String) {
+		self.data.push(line);
+	}
+
+	/// Turn this message into a delivery-status notification addressed back
+	/// to its sender, or `None` if it has no sender to notify (the `<>` null
+	/// reverse path, e.g. because this message was itself a bounce).
+	pub fn into_undeliverable(self) -> Option<Message> {
+		let sender = match self.reverse_path {
+			ReversePath::Null => return None,
+			ReversePath::Regular(path) => path,
+		};
+
+		Some(Message {
+			reverse_path: ReversePath::Null,
+			forward_paths: vec![ForwardPath::Regular(sender)],
+			data: vec!["The following message could not be delivered:".into()],
+		})
+	}
+}
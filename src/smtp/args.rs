@@ -0,0 +1,78 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// A domain as it appears in a `Path`, e.g. the part after the `@`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Domain(pub String);
+
+impl FromStr for Domain {
+	type Err = std::convert::Infallible;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Ok(Domain(s.to_string()))
+	}
+}
+
+impl Display for Domain {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+/// The part of a `Path` before the `@`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LocalPart(pub String);
+
+impl Display for LocalPart {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+/// A full `local-part@domain` mailbox path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Path {
+	pub local_part: LocalPart,
+	pub domain: Domain,
+}
+
+impl Display for Path {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}@{}", self.local_part, self.domain)
+	}
+}
+
+/// The `MAIL FROM` path. `Null` is the `<>` reverse path used on bounces and
+/// other messages that must not themselves bounce.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ReversePath {
+	#[default]
+	Null,
+	Regular(Path),
+}
+
+impl Display for ReversePath {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Null => write!(f, "<>"),
+			Self::Regular(path) => write!(f, "<{}>", path),
+		}
+	}
+}
+
+/// The `RCPT TO` path. `Postmaster` is the bare `<postmaster>` mailbox
+/// required by RFC 5321 to always be deliverable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForwardPath {
+	Postmaster,
+	Regular(Path),
+}
+
+impl Display for ForwardPath {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Postmaster => write!(f, "<postmaster>"),
+			Self::Regular(path) => write!(f, "<{}>", path),
+		}
+	}
+}
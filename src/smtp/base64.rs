@@ -0,0 +1,53 @@
+//! A small standalone base64 (RFC 4648 §4) encoder, used for the
+//! `AUTH PLAIN`/`AUTH LOGIN` exchange. Sail has no dependency on an external
+//! crate for this, so it's hand-rolled here rather than pulled in for one
+//! call site.
+
+const ALPHABET: &[u8; 64] =
+	b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(bytes: &[u8]) -> String {
+	let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+	for chunk in bytes.chunks(3) {
+		let b0 = chunk[0];
+		let b1 = *chunk.get(1).unwrap_or(&0);
+		let b2 = *chunk.get(2).unwrap_or(&0);
+
+		out.push(ALPHABET[(b0 >> 2) as usize] as char);
+		out.push(ALPHABET[((b0 & 0b11) << 4 | b1 >> 4) as usize] as char);
+		out.push(if chunk.len() > 1 {
+			ALPHABET[((b1 & 0b1111) << 2 | b2 >> 6) as usize] as char
+		} else {
+			'='
+		});
+		out.push(if chunk.len() > 2 {
+			ALPHABET[(b2 & 0b111111) as usize] as char
+		} else {
+			'='
+		});
+	}
+
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn encodes_known_vectors() {
+		assert_eq!(encode(b""), "");
+		assert_eq!(encode(b"f"), "Zg==");
+		assert_eq!(encode(b"fo"), "Zm8=");
+		assert_eq!(encode(b"foo"), "Zm9v");
+		assert_eq!(encode(b"foob"), "Zm9vYg==");
+		assert_eq!(encode(b"fooba"), "Zm9vYmE=");
+		assert_eq!(encode(b"foobar"), "Zm9vYmFy");
+	}
+
+	#[test]
+	fn encodes_an_auth_plain_initial_response() {
+		assert_eq!(encode(b"\0a\0b"), "AGEAYg==");
+	}
+}
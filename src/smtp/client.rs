@@ -1,11 +1,170 @@
 use std::fmt::Display;
 
+use crate::config::{Credentials, TlsPolicy};
+
 use super::{
 	args::{ForwardPath, Path, ReversePath},
+	base64,
 	Command::*,
-	Message, ResponseCode,
+	Message, Parameter, ResponseCode,
 };
 
+/// The ESMTP keywords a peer advertised in its EHLO response (RFC 5321
+/// §4.1.1.1, RFC 1869), so later states can act on them instead of just
+/// assuming a plain RFC 821 server.
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+	pub starttls: bool,
+	pub auth: Vec<String>,
+	pub size: Option<u64>,
+	pub eightbitmime: bool,
+	pub smtputf8: bool,
+}
+
+impl Capabilities {
+	/// Parse the keyword lines of an EHLO `250` reply. `lines` is every line
+	/// of the reply with its code and separator already stripped; the first
+	/// is the greeting text and the rest are the advertised keywords.
+	fn parse(lines: &[String]) -> Self {
+		let mut caps = Self::default();
+
+		for line in lines.iter().skip(1) {
+			let mut words = line.split_whitespace();
+			let keyword = match words.next() {
+				Some(keyword) => keyword.to_ascii_uppercase(),
+				None => continue,
+			};
+
+			match keyword.as_str() {
+				"STARTTLS" => caps.starttls = true,
+				"8BITMIME" => caps.eightbitmime = true,
+				"SMTPUTF8" => caps.smtputf8 = true,
+				"SIZE" => caps.size = words.next().and_then(|size| size.parse().ok()),
+				"AUTH" => caps.auth = words.map(|mechanism| mechanism.to_ascii_uppercase()).collect(),
+				_ => {}
+			}
+		}
+
+		caps
+	}
+}
+
+/// What a `ForeignMessage` needs from the peer's ESMTP extensions in order
+/// to be relayed faithfully. Body and address needs are tracked separately,
+/// since they can only be satisfied by different (if overlapping) sets of
+/// extensions: a non-ASCII envelope address needs `SMTPUTF8` specifically,
+/// while a non-ASCII body can go out under either `8BITMIME` or `SMTPUTF8`.
+#[derive(Debug, Clone, Copy)]
+struct EncodingRequirement {
+	/// The size, in octets, MAIL FROM would declare via `SIZE=`.
+	size: u64,
+	/// The body contains octets outside the 7-bit ASCII range.
+	needs_8bit_body: bool,
+	/// The reverse path or a forward path contains octets outside the
+	/// 7-bit ASCII range.
+	needs_utf8_address: bool,
+}
+
+impl EncodingRequirement {
+	fn analyze(message: &ForeignMessage) -> Self {
+		let size = message.data.iter().map(|line| line.len() as u64 + 2).sum();
+		let needs_8bit_body = message.data.iter().any(|line| !line.is_ascii());
+		let needs_utf8_address = !message.reverse_path.to_string().is_ascii()
+			|| message
+				.forward_paths
+				.iter()
+				.any(|path| !path.0.to_string().is_ascii());
+
+		Self {
+			size,
+			needs_8bit_body,
+			needs_utf8_address,
+		}
+	}
+
+	/// Whether `capabilities` covers everything this message needs to be
+	/// relayed faithfully.
+	fn is_satisfiable(&self, capabilities: &Capabilities) -> bool {
+		if self.needs_utf8_address && !capabilities.smtputf8 {
+			return false;
+		}
+
+		if self.needs_8bit_body && !capabilities.eightbitmime && !capabilities.smtputf8 {
+			return false;
+		}
+
+		true
+	}
+}
+
+#[cfg(test)]
+mod encoding_requirement_tests {
+	use super::*;
+	use crate::smtp::args::{Domain, LocalPart};
+
+	fn ascii_message() -> ForeignMessage {
+		ForeignMessage::from_parts(
+			ReversePath::Regular(Path {
+				local_part: LocalPart("alice".into()),
+				domain: Domain("example.com".into()),
+			}),
+			vec![ForeignPath(Path {
+				local_part: LocalPart("bob".into()),
+				domain: Domain("example.net".into()),
+			})],
+			vec!["Hello!".into()],
+		)
+	}
+
+	#[test]
+	fn plain_ascii_message_needs_nothing() {
+		let requirement = EncodingRequirement::analyze(&ascii_message());
+		assert!(!requirement.needs_8bit_body);
+		assert!(!requirement.needs_utf8_address);
+		assert!(requirement.is_satisfiable(&Capabilities::default()));
+	}
+
+	#[test]
+	fn eightbitmime_alone_cannot_satisfy_a_utf8_address() {
+		let mut message = ascii_message();
+		message.forward_paths[0].0.local_part = LocalPart("bjöörn".into());
+
+		let requirement = EncodingRequirement::analyze(&message);
+		assert!(requirement.needs_utf8_address);
+
+		let capabilities = Capabilities {
+			eightbitmime: true,
+			..Capabilities::default()
+		};
+		assert!(!requirement.is_satisfiable(&capabilities));
+	}
+
+	#[test]
+	fn smtputf8_alone_satisfies_an_8bit_body() {
+		let mut message = ascii_message();
+		message.data = vec!["héllo".into()];
+
+		let requirement = EncodingRequirement::analyze(&message);
+		assert!(requirement.needs_8bit_body);
+		assert!(!requirement.needs_utf8_address);
+
+		let capabilities = Capabilities {
+			smtputf8: true,
+			..Capabilities::default()
+		};
+		assert!(requirement.is_satisfiable(&capabilities));
+	}
+
+	#[test]
+	fn unsupported_8bit_body_is_unsatisfiable() {
+		let mut message = ascii_message();
+		message.data = vec!["héllo".into()];
+
+		let requirement = EncodingRequirement::analyze(&message);
+		assert!(!requirement.is_satisfiable(&Capabilities::default()));
+	}
+}
+
 /// A small wrapper around Path as a type-checked, compile-time feature to try
 // and stop us from doing stupid things and trying to relay local messages.
 #[derive(Debug, Clone)]
@@ -68,14 +227,40 @@ pub struct Client {
 	reply: String,
 	message: ForeignMessage,
 
+	/// The ESMTP keywords the peer advertised in its EHLO response. Empty
+	/// until `State::Greeted` is processed.
+	capabilities: Capabilities,
+
+	/// How strongly this relay requires STARTTLS, per `Config`.
+	tls_policy: TlsPolicy,
+	/// Whether we've already upgraded this connection to TLS, so we don't
+	/// try to STARTTLS a second time after the post-upgrade re-EHLO.
+	tls_active: bool,
+
+	/// Credentials to authenticate with, if this relay requires login.
+	credentials: Option<Credentials>,
+
+	/// Set by `fail_policy` when this host itself couldn't satisfy a policy
+	/// (e.g. `TlsPolicy::Require` against a peer with no `STARTTLS`), rather
+	/// than any forward path being resolved one way or the other.
+	policy_failure: bool,
+
 	last_sent_path: Option<ForeignPath>,
+	accepted_forward_paths: Vec<ForeignPath>,
+	deferred_forward_paths: Vec<ForeignPath>,
 	rejected_forward_paths: Vec<ForeignPath>,
 }
 
 impl Client {
-	pub fn initiate(message: ForeignMessage) -> Self {
+	pub fn initiate(
+		message: ForeignMessage,
+		tls_policy: TlsPolicy,
+		credentials: Option<Credentials>,
+	) -> Self {
 		Self {
 			message,
+			tls_policy,
+			credentials,
 			..Default::default()
 		}
 	}
@@ -83,13 +268,30 @@ impl Client {
 	pub fn push(&mut self, reply: &str) -> Option<Output> {
 		self.reply.push_str(reply);
 
-		if !self.reply.ends_with("\r\n") {
+		if !self.reply.ends_with("\r\n") || !self.reply_is_complete() {
 			return None;
 		}
 
 		self.process_reply()
 	}
 
+	/// A reply is complete once its last line is terminal: a multiline reply
+	/// (RFC 5321 §4.2.1) has continuation lines like `250-TEXT\r\n` and ends
+	/// with a line like `250 TEXT\r\n`, where the 4th byte is a space rather
+	/// than a hyphen.
+	fn reply_is_complete(&self) -> bool {
+		self.reply
+			.lines()
+			.last()
+			.and_then(|line| line.as_bytes().get(3))
+			.map(|&byte| byte == b' ')
+			.unwrap_or(false)
+	}
+
+	/// A permanently-rejected message to bounce back to the sender, or `None`
+	/// if nothing was permanently rejected. Transient failures are handled
+	/// separately by `deferred`, so this reports only `5xx`-class replies and
+	/// the policy rejections of `reject_entire_message` and `fail_policy`.
 	pub fn undeliverable(self) -> Option<Message> {
 		if !self.rejected_forward_paths.is_empty() {
 			if let Some(mut msg) = Into::<Message>::into(self.message).into_undeliverable() {
@@ -106,20 +308,221 @@ impl Client {
 		}
 	}
 
-	fn invalid_forward(&mut self) {
-		self.rejected_forward_paths
-			.push(self.last_sent_path.take().unwrap())
+	/// A message containing just the forward paths that received a `4xx`
+	/// reply, for the driving loop to retry later with backoff, or `None`
+	/// if nothing was deferred.
+	pub fn deferred(&self) -> Option<ForeignMessage> {
+		if self.deferred_forward_paths.is_empty() {
+			return None;
+		}
+
+		Some(ForeignMessage {
+			reverse_path: self.message.reverse_path.clone(),
+			forward_paths: self.deferred_forward_paths.clone(),
+			data: self.message.data.clone(),
+		})
 	}
 
-	fn process_reply(&mut self) -> Option<Output> {
-		if self.reply.len() < 3 || !self.reply.is_ascii() {
+	/// The final outcome of every forward path this session touched.
+	pub fn forward_path_statuses(&self) -> Vec<(ForeignPath, DeliveryStatus)> {
+		self.accepted_forward_paths
+			.iter()
+			.cloned()
+			.map(|path| (path, DeliveryStatus::Delivered))
+			.chain(
+				self.deferred_forward_paths
+					.iter()
+					.cloned()
+					.map(|path| (path, DeliveryStatus::Deferred)),
+			)
+			.chain(
+				self.rejected_forward_paths
+					.iter()
+					.cloned()
+					.map(|path| (path, DeliveryStatus::Bounced)),
+			)
+			.collect()
+	}
+
+	/// Record the outcome of the forward path we just sent `RCPT` for.
+	fn invalid_forward(&mut self, code: ResponseCode) {
+		let path = self.last_sent_path.take().unwrap();
+
+		if code.is_permanent() {
+			self.rejected_forward_paths.push(path);
+		} else {
+			self.deferred_forward_paths.push(path);
+		}
+	}
+
+	/// End the session, sorting every forward path we haven't resolved yet
+	/// - whichever we were mid-RCPT for, whichever never got an RCPT, and
+	/// whichever already got a positive RCPT but lost the envelope to a
+	/// later failure - into either the rejected or deferred set.
+	fn end_session(&mut self, permanent: bool) -> Output {
+		let unresolved: Vec<ForeignPath> = self
+			.last_sent_path
+			.take()
+			.into_iter()
+			.chain(self.message.forward_paths.drain(..))
+			.chain(self.accepted_forward_paths.drain(..))
+			.collect();
+
+		if permanent {
+			self.rejected_forward_paths.extend(unresolved);
+		} else {
+			self.deferred_forward_paths.extend(unresolved);
+		}
+
+		self.state = State::ShouldExit;
+		Output::Command(Quit)
+	}
+
+	/// Reject every forward path still outstanding and end the session. Used
+	/// when a policy (failed AUTH, an unsatisfiable encoding requirement)
+	/// can't be satisfied, rather than by a specific reply code.
+	fn reject_entire_message(&mut self) -> Output {
+		self.end_session(true)
+	}
+
+	/// Reject every forward path still outstanding, same as
+	/// `reject_entire_message`, but also flag that the *host* - not the
+	/// message - was the problem (no usable STARTTLS against a
+	/// `TlsPolicy::Require` relay). Until a multi-host driver exists to act
+	/// on `policy_failure`, the message is bounced via `undeliverable` like
+	/// any other rejection rather than silently dropped; a future driver
+	/// following a `Route` can check `policy_failure()` to retry against the
+	/// next candidate instead.
+	fn fail_policy(&mut self) -> Output {
+		self.policy_failure = true;
+		self.end_session(true)
+	}
+
+	/// Whether this session ended via `fail_policy` rather than a specific
+	/// reply code. A driver following a `Route` can use this to decide
+	/// whether retrying against another host is worth it, instead of
+	/// treating every rejection the same.
+	pub fn policy_failure(&self) -> bool {
+		self.policy_failure
+	}
+
+	/// Move past the EHLO/STARTTLS/AUTH dance and start the envelope.
+	fn send_reverse_path(&mut self) -> Output {
+		let requirement = EncodingRequirement::analyze(&self.message);
+
+		if !requirement.is_satisfiable(&self.capabilities) {
+			return self.reject_entire_message();
+		}
+
+		let mut params = Vec::new();
+		if self.capabilities.size.is_some() {
+			params.push(Parameter::Size(requirement.size));
+		}
+		if requirement.needs_8bit_body && self.capabilities.eightbitmime {
+			params.push(Parameter::Body8BitMime);
+		}
+		// A UTF-8 address needs SMTPUTF8 regardless of how the body is
+		// declared (RFC 6531); an 8-bit body can also fall back to it when
+		// 8BITMIME isn't available, so the two checks aren't exclusive.
+		if self.capabilities.smtputf8
+			&& (requirement.needs_utf8_address || (requirement.needs_8bit_body && !self.capabilities.eightbitmime))
+		{
+			params.push(Parameter::SmtpUtf8);
+		}
+
+		self.state = State::SentReversePath;
+		Output::Command(Mail(self.message.reverse_path.clone(), params))
+	}
+
+	/// We've just landed in (or returned to) `State::Greeted` with fresh
+	/// `capabilities`. Decide whether to negotiate STARTTLS, refuse for lack
+	/// of it, or move on to authentication.
+	fn proceed_past_ehlo(&mut self) -> Output {
+		if !self.tls_active && self.capabilities.starttls && self.tls_policy != TlsPolicy::Disable {
+			self.state = State::SentStarttls;
+			return Output::Command(Starttls);
+		}
+
+		if !self.tls_active && self.tls_policy == TlsPolicy::Require {
+			return self.fail_policy();
+		}
+
+		self.begin_auth()
+	}
+
+	/// TLS (if any) is settled; authenticate if we have credentials and the
+	/// peer supports a mechanism we speak, otherwise start the envelope.
+	fn begin_auth(&mut self) -> Output {
+		let credentials = match &self.credentials {
+			Some(credentials) => credentials,
+			None => return self.send_reverse_path(),
+		};
+
+		if self.capabilities.auth.iter().any(|m| m == "PLAIN") {
+			self.state = State::Authenticating(AuthStage::Plain);
+			let initial_response = base64::encode(
+				format!("\0{}\0{}", credentials.username, credentials.password).as_bytes(),
+			);
+			return Output::Command(AuthPlain(initial_response));
+		}
+
+		if self.capabilities.auth.iter().any(|m| m == "LOGIN") {
+			self.state = State::Authenticating(AuthStage::LoginUsername);
+			return Output::Command(AuthLogin);
+		}
+
+		// We have credentials for this relay but it didn't advertise a
+		// mechanism we speak. Relaying anonymously would silently bypass the
+		// operator's intent to authenticate, so treat this as a rejection of
+		// the whole message rather than falling through unauthenticated.
+		self.reject_entire_message()
+	}
+
+	/// Split the buffered reply into its shared numeric code and the text of
+	/// each line, with the code and code/text separator stripped. Returns
+	/// `None` if the lines don't agree on a single code, since peers must
+	/// not change the code across a multiline reply.
+	fn take_reply_lines(&mut self) -> Option<(u16, Vec<String>)> {
+		if !self.reply.is_ascii() {
+			self.reply.clear();
 			return None;
 		}
-		let code = self.reply.split_at(3).0;
 
-		//todo: parse multiline replies e.g. ehlo
+		let mut code = None;
+		let mut lines = Vec::new();
+
+		for line in self.reply.lines() {
+			if line.len() < 4 {
+				self.reply.clear();
+				return None;
+			}
+
+			let (line_code, rest) = line.split_at(3);
+			let line_code: u16 = match line_code.parse() {
+				Ok(line_code) => line_code,
+				Err(_) => {
+					self.reply.clear();
+					return None;
+				}
+			};
+
+			if *code.get_or_insert(line_code) != line_code {
+				self.reply.clear();
+				return None;
+			}
+
+			lines.push(rest[1..].to_string());
+		}
+
+		self.reply.clear();
+		Some((code?, lines))
+	}
+
+	fn process_reply(&mut self) -> Option<Output> {
+		let (code, lines) = self.take_reply_lines()?;
+
 		//todo: handle the unknown response codes
-		let code = ResponseCode::from_code(code.parse().ok()?)?;
+		let code = ResponseCode::from_code(code)?;
 
 		Some(match self.state {
 			State::Initiated => match code {
@@ -127,54 +530,93 @@ impl Client {
 					self.state = State::Greeted;
 					Output::Command(Ehlo("Sail".parse().unwrap())) //todo: use actual hostname, not Sail
 				}
-				_ => todo!(),
+				// No recipient has been attempted yet, so a bad greeting
+				// fails the whole message rather than any one path.
+				_ => self.end_session(code.is_permanent()),
 			},
 			State::Greeted => match code {
 				ResponseCode::Okay => {
-					self.state = State::SentReversePath;
-					Output::Command(Mail(self.message.reverse_path.clone()))
+					self.capabilities = Capabilities::parse(&lines);
+					self.proceed_past_ehlo()
 				}
-				_ => todo!(),
+				_ => self.end_session(code.is_permanent()),
+			},
+			State::SentStarttls => match code {
+				ResponseCode::ServiceReady => {
+					// RFC 3207: discard anything learned before the TLS
+					// handshake and re-negotiate over the encrypted channel.
+					self.tls_active = true;
+					self.capabilities = Capabilities::default();
+					self.state = State::Greeted;
+					Output::UpgradeTls(Ehlo("Sail".parse().unwrap())) //todo: use actual hostname, not Sail
+				}
+				_ if self.tls_policy == TlsPolicy::Require => self.fail_policy(),
+				_ => self.begin_auth(),
+			},
+			State::Authenticating(stage) => match code {
+				ResponseCode::AuthSuccessful => self.send_reverse_path(),
+				ResponseCode::AuthFailed => self.reject_entire_message(),
+				ResponseCode::ServerChallenge => {
+					let credentials = self.credentials.as_ref().unwrap();
+
+					match stage {
+						AuthStage::LoginUsername => {
+							let response = base64::encode(credentials.username.as_bytes());
+							self.state = State::Authenticating(AuthStage::LoginPassword);
+							Output::Command(AuthResponse(response))
+						}
+						AuthStage::LoginPassword => {
+							Output::Command(AuthResponse(base64::encode(credentials.password.as_bytes())))
+						}
+						// AUTH PLAIN sends its whole response up front, so a
+						// server challenging us mid-exchange is a protocol
+						// error we don't know how to answer.
+						AuthStage::Plain => self.reject_entire_message(),
+					}
+				}
+				_ => self.end_session(code.is_permanent()),
 			},
 			State::SentReversePath => match code {
 				ResponseCode::Okay => {
+					let path = self.message.forward_paths.pop()?;
+					self.last_sent_path = Some(path.clone());
 					self.state = State::SendingForwardPaths;
-					Output::Command(Rcpt(self.message.forward_paths.pop()?.into()))
+					Output::Command(Rcpt(path.into(), Vec::new()))
 				}
-				_ => todo!(),
+				// MAIL failed before any recipient was attempted: the whole
+				// message is rejected or deferred, not any one path.
+				_ => self.end_session(code.is_permanent()),
 			},
 			State::SendingForwardPaths => {
 				if code.is_negative() {
-					self.invalid_forward();
+					self.invalid_forward(code);
+				} else if let Some(path) = self.last_sent_path.take() {
+					self.accepted_forward_paths.push(path);
 				}
 
 				if let Some(path) = self.message.forward_paths.pop() {
 					self.last_sent_path = Some(path.clone());
-					Output::Command(Rcpt(path.into()))
+					Output::Command(Rcpt(path.into(), Vec::new()))
 				} else {
 					self.state = State::SentForwardPaths;
 					Output::Command(Data)
 				}
 			}
-			State::SentForwardPaths => {
-				if code.is_negative() {
-					self.invalid_forward();
+			State::SentForwardPaths => match code {
+				ResponseCode::StartMailInput => {
+					self.state = State::SentData;
+					Output::Data(self.message.data.clone())
 				}
-
-				match code {
-					ResponseCode::StartMailInput => {
-						self.state = State::SentData;
-						Output::Data(self.message.data.clone())
-					}
-					_ => todo!(),
-				}
-			}
+				// DATA itself was refused: every recipient we'd already had
+				// accepted loses the envelope along with it.
+				_ => self.end_session(code.is_permanent()),
+			},
 			State::SentData => match code {
 				ResponseCode::Okay => {
 					self.state = State::ShouldExit;
 					Output::Command(Quit)
 				}
-				_ => todo!(),
+				_ => self.end_session(code.is_permanent()),
 			},
 			State::ShouldExit => unreachable!(),
 		})
@@ -185,10 +627,13 @@ impl Client {
 	}
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Default)]
 enum State {
+	#[default]
 	Initiated,
 	Greeted,
+	SentStarttls,
+	Authenticating(AuthStage),
 	SentReversePath,
 	SendingForwardPaths,
 	SentForwardPaths,
@@ -196,15 +641,32 @@ enum State {
 	ShouldExit,
 }
 
-impl Default for State {
-	fn default() -> Self {
-		State::Initiated
-	}
+/// Where we are in the `AUTH LOGIN`/`AUTH PLAIN` challenge-response
+/// exchange.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AuthStage {
+	Plain,
+	LoginUsername,
+	LoginPassword,
+}
+
+/// The final outcome of a single forward path, per RFC 5321's reply classes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryStatus {
+	/// The peer accepted the message for this recipient.
+	Delivered,
+	/// A `4xx` reply; worth retrying later.
+	Deferred,
+	/// A `5xx` reply; retrying without changes won't help.
+	Bounced,
 }
 
 pub enum Output {
 	Command(super::Command),
 	Data(Vec<String>),
+	/// The transport must perform a TLS handshake on the underlying
+	/// connection before sending the enclosed command over it.
+	UpgradeTls(super::Command),
 }
 
 impl Display for Output {
@@ -212,6 +674,148 @@ impl Display for Output {
 		match self {
 			Self::Command(command) => write!(f, "{}", command),
 			Self::Data(data) => write!(f, "{}\r\n.\r\n", data.join("\r\n")),
+			Self::UpgradeTls(command) => write!(f, "{}", command),
 		}
 	}
 }
+
+#[cfg(test)]
+mod client_tests {
+	use super::*;
+	use crate::smtp::args::{Domain, LocalPart};
+
+	#[test]
+	fn capabilities_parse_recognizes_every_keyword() {
+		let lines: Vec<String> = vec![
+			"mail.example.com greets you".into(),
+			"STARTTLS".into(),
+			"8BITMIME".into(),
+			"SMTPUTF8".into(),
+			"SIZE 1048576".into(),
+			"AUTH PLAIN LOGIN".into(),
+		];
+
+		let caps = Capabilities::parse(&lines);
+		assert!(caps.starttls);
+		assert!(caps.eightbitmime);
+		assert!(caps.smtputf8);
+		assert_eq!(caps.size, Some(1048576));
+		assert_eq!(caps.auth, vec!["PLAIN", "LOGIN"]);
+	}
+
+	#[test]
+	fn capabilities_parse_ignores_unknown_keywords() {
+		let lines: Vec<String> = vec!["greeting".into(), "DSN".into()];
+		let caps = Capabilities::parse(&lines);
+		assert!(!caps.starttls);
+		assert!(caps.auth.is_empty());
+	}
+
+	#[test]
+	fn take_reply_lines_clears_buffer_on_non_numeric_status() {
+		let mut client = Client::default();
+		client.push("25x Not a number\r\n");
+
+		assert!(client.reply.is_empty());
+
+		// A corrupted buffer must not bleed into the next reply: pushing a
+		// well-formed one afterward should parse cleanly.
+		let output = client.push("220 mail.example.com ESMTP\r\n");
+		assert!(output.is_some());
+	}
+
+	#[test]
+	fn take_reply_lines_clears_buffer_on_mismatched_multiline_codes() {
+		let mut client = Client::default();
+		let output = client.push("250-first\r\n251 second\r\n");
+
+		assert!(output.is_none());
+		assert!(client.reply.is_empty());
+	}
+
+	#[test]
+	fn tls_required_but_unsupported_flags_policy_failure_and_still_bounces_the_message() {
+		let message = ForeignMessage::from_parts(
+			ReversePath::Regular(Path {
+				local_part: LocalPart("alice".into()),
+				domain: Domain("example.com".into()),
+			}),
+			vec![ForeignPath(Path {
+				local_part: LocalPart("bob".into()),
+				domain: Domain("example.net".into()),
+			})],
+			vec![],
+		);
+		let mut client = Client::initiate(message, TlsPolicy::Require, None);
+		client.push("220 mail.example.com ESMTP\r\n");
+		let output = client.push("250-mail.example.com\r\n250 SIZE 1000\r\n");
+
+		assert!(matches!(output, Some(Output::Command(Quit))));
+		assert!(client.policy_failure());
+		// Until a multi-host driver exists to act on `policy_failure`, the
+		// message still has to land somewhere instead of disappearing.
+		assert!(client.undeliverable().is_some());
+	}
+
+	#[test]
+	fn eightbit_body_with_only_smtputf8_support_is_declared_via_smtputf8() {
+		let message = ForeignMessage::from_parts(ReversePath::Null, vec![], vec!["héllo".into()]);
+		let mut client = Client::initiate(message, TlsPolicy::Disable, None);
+		client.push("220 mail.example.com ESMTP\r\n");
+		let output = client
+			.push("250-mail.example.com\r\n250 SMTPUTF8\r\n")
+			.expect("MAIL FROM should be sent once capabilities are known");
+
+		match output {
+			Output::Command(Mail(_, params)) => {
+				assert!(
+					params.iter().any(|param| matches!(param, Parameter::SmtpUtf8)),
+					"expected SMTPUTF8 to be declared for an 8-bit body when only SMTPUTF8 is supported"
+				);
+			}
+			_ => panic!("expected a MAIL command"),
+		}
+	}
+
+	#[test]
+	fn utf8_address_and_8bit_body_declare_both_parameters_when_both_are_supported() {
+		let message = ForeignMessage::from_parts(
+			ReversePath::Regular(Path {
+				local_part: LocalPart("bjöörn".into()),
+				domain: Domain("example.com".into()),
+			}),
+			vec![],
+			vec!["héllo".into()],
+		);
+		let mut client = Client::initiate(message, TlsPolicy::Disable, None);
+		client.push("220 mail.example.com ESMTP\r\n");
+		let output = client
+			.push("250-mail.example.com\r\n250-8BITMIME\r\n250 SMTPUTF8\r\n")
+			.expect("MAIL FROM should be sent once capabilities are known");
+
+		match output {
+			Output::Command(Mail(_, params)) => {
+				assert!(params.iter().any(|param| matches!(param, Parameter::Body8BitMime)));
+				assert!(
+					params.iter().any(|param| matches!(param, Parameter::SmtpUtf8)),
+					"a UTF-8 address must declare SMTPUTF8 even when BODY=8BITMIME is also declared"
+				);
+			}
+			_ => panic!("expected a MAIL command"),
+		}
+	}
+
+	#[test]
+	fn auth_configured_but_no_supported_mechanism_rejects_the_message() {
+		let credentials = Credentials {
+			username: "alice".into(),
+			password: "hunter2".into(),
+		};
+		let mut client = Client::initiate(ForeignMessage::default(), TlsPolicy::Disable, Some(credentials));
+		client.push("220 mail.example.com ESMTP\r\n");
+		let output = client.push("250-mail.example.com\r\n250 DSN\r\n");
+
+		assert!(matches!(output, Some(Output::Command(Quit))));
+		assert!(!client.policy_failure());
+	}
+}
@@ -0,0 +1,73 @@
+use std::fmt::{self, Display};
+
+use super::args::{Domain, ForwardPath, ReversePath};
+
+/// An ESMTP parameter attached to a `MAIL` or `RCPT` command (RFC 1869 §5).
+#[derive(Debug, Clone)]
+pub enum Parameter {
+	/// `SIZE=<bytes>` (RFC 1870), the size of the message being offered.
+	Size(u64),
+	/// `BODY=8BITMIME` (RFC 6152), the message contains 8-bit data.
+	Body8BitMime,
+	/// `SMTPUTF8` (RFC 6531), an address or the body needs UTF-8.
+	SmtpUtf8,
+}
+
+impl Display for Parameter {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Size(bytes) => write!(f, "SIZE={}", bytes),
+			Self::Body8BitMime => write!(f, "BODY=8BITMIME"),
+			Self::SmtpUtf8 => write!(f, "SMTPUTF8"),
+		}
+	}
+}
+
+fn write_params(f: &mut fmt::Formatter<'_>, params: &[Parameter]) -> fmt::Result {
+	for param in params {
+		write!(f, " {}", param)?;
+	}
+	Ok(())
+}
+
+/// A command the `Client` can send to a peer, and its wire representation.
+#[derive(Debug, Clone)]
+pub enum Command {
+	Ehlo(Domain),
+	Starttls,
+	/// `AUTH PLAIN <initial-response>`, already base64-encoded.
+	AuthPlain(String),
+	/// Bare `AUTH LOGIN`; the server will challenge for username then
+	/// password with `334` replies.
+	AuthLogin,
+	/// A base64-encoded response to a `334` AUTH challenge.
+	AuthResponse(String),
+	Mail(ReversePath, Vec<Parameter>),
+	Rcpt(ForwardPath, Vec<Parameter>),
+	Data,
+	Quit,
+}
+
+impl Display for Command {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Ehlo(domain) => write!(f, "EHLO {}\r\n", domain),
+			Self::Starttls => write!(f, "STARTTLS\r\n"),
+			Self::AuthPlain(initial_response) => write!(f, "AUTH PLAIN {}\r\n", initial_response),
+			Self::AuthLogin => write!(f, "AUTH LOGIN\r\n"),
+			Self::AuthResponse(response) => write!(f, "{}\r\n", response),
+			Self::Mail(reverse_path, params) => {
+				write!(f, "MAIL FROM:{}", reverse_path)?;
+				write_params(f, params)?;
+				write!(f, "\r\n")
+			}
+			Self::Rcpt(forward_path, params) => {
+				write!(f, "RCPT TO:{}", forward_path)?;
+				write_params(f, params)?;
+				write!(f, "\r\n")
+			}
+			Self::Data => write!(f, "DATA\r\n"),
+			Self::Quit => write!(f, "QUIT\r\n"),
+		}
+	}
+}
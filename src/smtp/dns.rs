@@ -0,0 +1,228 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+use super::args::Domain;
+use super::route::Resolver;
+
+const QTYPE_MX: u16 = 15;
+const QCLASS_IN: u16 = 1;
+
+/// A minimal synchronous DNS client that can do exactly one thing: ask a
+/// resolver for a domain's `MX` records over UDP (RFC 1035). No caching, no
+/// TCP fallback for truncated responses, no retries - enough to make
+/// `Config::route` actually resolve something.
+#[derive(Debug, Clone)]
+pub struct DnsResolver {
+	nameserver: SocketAddr,
+	timeout: Duration,
+}
+
+impl DnsResolver {
+	pub fn new(nameserver: SocketAddr) -> Self {
+		Self {
+			nameserver,
+			timeout: Duration::from_secs(5),
+		}
+	}
+
+	/// Build a resolver pointed at the first `nameserver` line of
+	/// `/etc/resolv.conf`, falling back to `fallback` if the file is
+	/// missing or has none.
+	pub fn from_resolv_conf(fallback: SocketAddr) -> Self {
+		let nameserver = std::fs::read_to_string("/etc/resolv.conf")
+			.ok()
+			.and_then(|conf| {
+				conf.lines().find_map(|line| {
+					let rest = line.trim().strip_prefix("nameserver")?;
+					let ip: IpAddr = rest.trim().parse().ok()?;
+					Some(SocketAddr::new(ip, 53))
+				})
+			})
+			.unwrap_or(fallback);
+
+		Self::new(nameserver)
+	}
+
+	/// A transaction ID that's unpredictable enough to make blind off-path
+	/// UDP spoofing require a guess, not just a listener on the right port.
+	/// `RandomState` seeds itself from the OS's random source, so this gets
+	/// real entropy without pulling in a `rand` dependency.
+	fn transaction_id() -> u16 {
+		RandomState::new().build_hasher().finish() as u16
+	}
+
+	fn query(&self, domain: &Domain) -> std::io::Result<Vec<(u16, Domain)>> {
+		let id = Self::transaction_id();
+
+		let mut packet = Vec::new();
+		packet.extend_from_slice(&id.to_be_bytes());
+		packet.extend_from_slice(&[0x01, 0x00]); // flags: RD=1
+		packet.extend_from_slice(&[0x00, 0x01]); // QDCOUNT
+		packet.extend_from_slice(&[0x00, 0x00]); // ANCOUNT
+		packet.extend_from_slice(&[0x00, 0x00]); // NSCOUNT
+		packet.extend_from_slice(&[0x00, 0x00]); // ARCOUNT
+
+		for label in domain.0.split('.').filter(|label| !label.is_empty()) {
+			packet.push(label.len() as u8);
+			packet.extend_from_slice(label.as_bytes());
+		}
+		packet.push(0); // root label
+		packet.extend_from_slice(&QTYPE_MX.to_be_bytes());
+		packet.extend_from_slice(&QCLASS_IN.to_be_bytes());
+
+		let socket = UdpSocket::bind("0.0.0.0:0")?;
+		socket.set_read_timeout(Some(self.timeout))?;
+		socket.set_write_timeout(Some(self.timeout))?;
+		// Connecting the socket makes the kernel drop any datagram not from
+		// `nameserver`, so a reply has to come from the resolver we asked.
+		socket.connect(self.nameserver)?;
+		socket.send(&packet)?;
+
+		let mut buf = [0u8; 512];
+		loop {
+			let len = socket.recv(&mut buf)?;
+			if len >= 2 && u16::from_be_bytes([buf[0], buf[1]]) == id {
+				return Ok(parse_mx_response(&buf[..len]));
+			}
+			// Stale or spoofed reply for a different transaction; keep
+			// waiting until the read timeout elapses.
+		}
+	}
+}
+
+impl Resolver for DnsResolver {
+	fn lookup_mx(&self, domain: &Domain) -> Vec<Domain> {
+		let mut records = match self.query(domain) {
+			Ok(records) => records,
+			Err(_) => return Vec::new(),
+		};
+
+		records.sort_by_key(|(preference, _)| *preference);
+		records.into_iter().map(|(_, domain)| domain).collect()
+	}
+}
+
+/// Read a (possibly compressed) domain name starting at `pos` in `packet`,
+/// returning it and the offset just past it in the *original* message (not
+/// following any compression pointer).
+fn read_name(packet: &[u8], mut pos: usize) -> Option<(String, usize)> {
+	let mut labels = Vec::new();
+	let mut end = None;
+	let mut hops = 0;
+
+	loop {
+		hops += 1;
+		if hops > 128 {
+			return None; // pointer loop guard
+		}
+
+		let len = *packet.get(pos)?;
+
+		if len == 0 {
+			pos += 1;
+			break;
+		} else if len & 0b1100_0000 == 0b1100_0000 {
+			let lo = *packet.get(pos + 1)? as usize;
+			let pointer = ((len as usize & 0b0011_1111) << 8) | lo;
+
+			if end.is_none() {
+				end = Some(pos + 2);
+			}
+			pos = pointer;
+		} else {
+			let len = len as usize;
+			let label = packet.get(pos + 1..pos + 1 + len)?;
+			labels.push(String::from_utf8_lossy(label).into_owned());
+			pos += 1 + len;
+		}
+	}
+
+	Some((labels.join("."), end.unwrap_or(pos)))
+}
+
+fn parse_mx_response(packet: &[u8]) -> Vec<(u16, Domain)> {
+	if packet.len() < 12 {
+		return Vec::new();
+	}
+
+	let qdcount = u16::from_be_bytes([packet[4], packet[5]]) as usize;
+	let ancount = u16::from_be_bytes([packet[6], packet[7]]) as usize;
+
+	let mut pos = 12;
+	for _ in 0..qdcount {
+		let (_, after_name) = match read_name(packet, pos) {
+			Some(result) => result,
+			None => return Vec::new(),
+		};
+		pos = after_name + 4; // QTYPE + QCLASS
+	}
+
+	let mut records = Vec::new();
+	for _ in 0..ancount {
+		let (_, after_name) = match read_name(packet, pos) {
+			Some(result) => result,
+			None => break,
+		};
+		pos = after_name;
+
+		let header = match packet.get(pos..pos + 10) {
+			Some(header) => header,
+			None => break,
+		};
+		let rtype = u16::from_be_bytes([header[0], header[1]]);
+		let rdlength = u16::from_be_bytes([header[8], header[9]]) as usize;
+		pos += 10;
+
+		if rtype == QTYPE_MX {
+			if let Some(preference) = packet.get(pos..pos + 2) {
+				let preference = u16::from_be_bytes([preference[0], preference[1]]);
+				if let Some((exchange, _)) = read_name(packet, pos + 2) {
+					records.push((preference, Domain(exchange)));
+				}
+			}
+		}
+
+		pos += rdlength;
+	}
+
+	records
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reads_simple_name() {
+		// "mx.example.com" followed by the root label.
+		let mut packet = vec![0u8; 12];
+		packet.extend_from_slice(&[2, b'm', b'x', 7]);
+		packet.extend_from_slice(b"example");
+		packet.extend_from_slice(&[3, b'c', b'o', b'm', 0]);
+
+		let (name, end) = read_name(&packet, 12).unwrap();
+		assert_eq!(name, "mx.example.com");
+		assert_eq!(end, packet.len());
+	}
+
+	#[test]
+	fn follows_compression_pointer() {
+		let mut packet = vec![0u8; 12];
+		packet.extend_from_slice(&[3, b'c', b'o', b'm', 0]); // offset 12
+		let pointer_offset = packet.len();
+		packet.extend_from_slice(&[2, b'm', b'x', 0b1100_0000, 12]);
+
+		let (name, end) = read_name(&packet, pointer_offset).unwrap();
+		assert_eq!(name, "mx.com");
+		assert_eq!(end, packet.len());
+	}
+
+	#[test]
+	fn detects_pointer_loops_instead_of_hanging() {
+		let mut packet = vec![0u8; 12];
+		packet.extend_from_slice(&[0b1100_0000, 12]); // points at itself
+		assert!(read_name(&packet, 12).is_none());
+	}
+}
@@ -0,0 +1,15 @@
+pub mod args;
+mod base64;
+mod client;
+mod command;
+mod dns;
+mod response;
+mod route;
+
+pub use client::{Client, ForeignMessage, ForeignPath, Output};
+pub use command::{Command, Parameter};
+pub use dns::DnsResolver;
+pub use response::ResponseCode;
+pub use route::{NoMxResolver, RelayAttempt, Resolver, Route};
+
+pub use crate::message::Message;
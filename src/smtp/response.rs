@@ -0,0 +1,78 @@
+/// A parsed numeric SMTP reply code (RFC 5321 §4.2).
+///
+/// The well-known codes Sail acts on directly get their own variant;
+/// everything else is still classified by its leading digit so callers can
+/// make a correct success/transient/permanent decision even for codes we
+/// don't have special handling for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseCode {
+	/// 220, greeting / STARTTLS ready.
+	ServiceReady,
+	/// 235, authentication succeeded.
+	AuthSuccessful,
+	/// 250, generic success.
+	Okay,
+	/// 334, AUTH LOGIN/PLAIN server challenge.
+	ServerChallenge,
+	/// 354, go ahead and send DATA.
+	StartMailInput,
+	/// 535, authentication failed.
+	AuthFailed,
+	/// Any other 2xx/3xx we don't treat specially.
+	Other(u16),
+	/// 4xx: the request failed but may succeed if retried later.
+	TransientNegative(u16),
+	/// 5xx: the request failed and retrying verbatim won't help.
+	PermanentNegative(u16),
+}
+
+impl ResponseCode {
+	pub fn from_code(code: u16) -> Option<Self> {
+		Some(match code {
+			220 => Self::ServiceReady,
+			235 => Self::AuthSuccessful,
+			250 => Self::Okay,
+			334 => Self::ServerChallenge,
+			354 => Self::StartMailInput,
+			535 => Self::AuthFailed,
+			200..=399 => Self::Other(code),
+			400..=499 => Self::TransientNegative(code),
+			500..=599 => Self::PermanentNegative(code),
+			_ => return None,
+		})
+	}
+
+	pub fn code(&self) -> u16 {
+		match self {
+			Self::ServiceReady => 220,
+			Self::AuthSuccessful => 235,
+			Self::Okay => 250,
+			Self::ServerChallenge => 334,
+			Self::StartMailInput => 354,
+			Self::AuthFailed => 535,
+			Self::Other(code) | Self::TransientNegative(code) | Self::PermanentNegative(code) => {
+				*code
+			}
+		}
+	}
+
+	/// Whether this reply is a `4xx` or `5xx` negative reply.
+	pub fn is_negative(&self) -> bool {
+		matches!(
+			self,
+			Self::TransientNegative(_) | Self::PermanentNegative(_) | Self::AuthFailed
+		)
+	}
+
+	/// Whether this is a `4xx` reply: the command failed but retrying later
+	/// might succeed.
+	pub fn is_transient(&self) -> bool {
+		matches!(self, Self::TransientNegative(_))
+	}
+
+	/// Whether this is a `5xx` reply: the command failed and won't succeed
+	/// if retried as-is.
+	pub fn is_permanent(&self) -> bool {
+		matches!(self, Self::PermanentNegative(_) | Self::AuthFailed)
+	}
+}
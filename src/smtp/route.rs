@@ -0,0 +1,131 @@
+use crate::config::Config;
+
+use super::args::{Domain, ForwardPath};
+use super::client::{Client, ForeignMessage};
+
+/// Looks up the hosts that should be tried, in order, to deliver mail
+/// directly to a domain that isn't behind one of our configured relays.
+pub trait Resolver {
+	/// MX records for `domain`, already sorted by preference (RFC 5321
+	/// §5.1, lowest preference first). Empty if the domain has none.
+	fn lookup_mx(&self, domain: &Domain) -> Vec<Domain>;
+}
+
+/// A `Resolver` that never finds an MX record, so `Config::route` always
+/// falls back to the domain's own A/AAAA record.
+///
+/// This is `Config`'s default resolver; `DnsResolver` is the `Resolver` that
+/// does real MX lookups.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoMxResolver;
+
+impl Resolver for NoMxResolver {
+	fn lookup_mx(&self, _domain: &Domain) -> Vec<Domain> {
+		Vec::new()
+	}
+}
+
+/// The ordered list of hosts `Config::route` produced for one message. The
+/// driver that owns the actual connection pops hosts off the front and
+/// tries the next one whenever a connection attempt or initial greeting
+/// fails transiently.
+#[derive(Debug, Clone, Default)]
+pub struct Route(Vec<Domain>);
+
+impl Route {
+	pub fn new(hosts: Vec<Domain>) -> Self {
+		Self(hosts)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Take the next host to try, removing it from the queue.
+	pub fn next_host(&mut self) -> Option<Domain> {
+		if self.0.is_empty() {
+			None
+		} else {
+			Some(self.0.remove(0))
+		}
+	}
+}
+
+/// Drives a single recipient's `Route`, handing back a freshly-initiated
+/// `Client` for each host until either one succeeds or the route is
+/// exhausted. The transport layer owns the actual connection: it should
+/// keep calling `next_client` with the next host whenever a connection
+/// attempt or initial greeting fails transiently.
+///
+/// There's no driver wired up yet that retries a `Client::policy_failure()`
+/// session (e.g. `TlsPolicy::Require` against a peer with no `STARTTLS`)
+/// against the next host - today that still bounces via `undeliverable`
+/// like any other rejection. `policy_failure()` exists so a future driver
+/// can choose to retry such a message against the next candidate instead.
+pub struct RelayAttempt<'a> {
+	config: &'a Config,
+	hosts: Route,
+}
+
+impl<'a> RelayAttempt<'a> {
+	pub fn new(config: &'a Config, forward: &ForwardPath) -> Self {
+		Self {
+			config,
+			hosts: Route::new(config.route(forward)),
+		}
+	}
+
+	pub fn is_exhausted(&self) -> bool {
+		self.hosts.is_empty()
+	}
+
+	/// The next host to try and a `Client` initiated with whatever TLS
+	/// policy and credentials `Config` has on file for it, or `None` once
+	/// every candidate has been tried.
+	pub fn next_client(&mut self, message: ForeignMessage) -> Option<(Domain, Client)> {
+		let host = self.hosts.next_host()?;
+		let relay = self.config.relay_for(&host);
+		let tls_policy = relay.map(|relay| relay.tls).unwrap_or_default();
+		let credentials = relay.and_then(|relay| relay.auth.clone());
+
+		Some((host, Client::initiate(message, tls_policy, credentials)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config::Relay;
+
+	#[test]
+	fn route_pops_hosts_in_order_then_is_empty() {
+		let mut route = Route::new(vec![Domain("a.example.com".into()), Domain("b.example.com".into())]);
+
+		assert!(!route.is_empty());
+		assert_eq!(route.next_host(), Some(Domain("a.example.com".into())));
+		assert_eq!(route.next_host(), Some(Domain("b.example.com".into())));
+		assert_eq!(route.next_host(), None);
+		assert!(route.is_empty());
+	}
+
+	#[test]
+	fn relay_attempt_hands_out_the_configured_relay_once() {
+		let config = Config {
+			relays: vec![Relay {
+				domain: Domain("smarthost.example.com".into()),
+				tls: crate::config::TlsPolicy::Require,
+				auth: None,
+			}],
+			..Config::default()
+		};
+		let forward = ForwardPath::Postmaster;
+
+		let mut attempt = RelayAttempt::new(&config, &forward);
+		assert!(!attempt.is_exhausted());
+
+		let (host, _client) = attempt.next_client(ForeignMessage::default()).unwrap();
+		assert_eq!(host, Domain("smarthost.example.com".into()));
+		assert!(attempt.is_exhausted());
+		assert!(attempt.next_client(ForeignMessage::default()).is_none());
+	}
+}